@@ -0,0 +1,131 @@
+//! Pollard's kangaroo (lambda) method for discrete logs known to lie in a
+//! bounded interval `[a, b]` — O(sqrt(b - a)) time and constant memory,
+//! unlike baby-step-giant-step's `HashMap` sized to the full subgroup order.
+
+use num::bigint::BigUint;
+use num::{One, ToPrimitive, Zero};
+
+use crate::math;
+
+/// The wild kangaroo gives up after this many times the tame kangaroo's hop
+/// count (its head start).
+const HOP_BUDGET_FACTOR: u32 = 4;
+
+/// Solves `g^x = h (mod p)` for `x` in `[a, b]`, or `None` if no such `x` is
+/// found within the hop budget.
+pub(crate) fn pollard_kangaroo(
+    g: &BigUint,
+    h: &BigUint,
+    p: &BigUint,
+    a: &BigUint,
+    b: &BigUint,
+) -> Option<BigUint> {
+    if b < a {
+        return None;
+    }
+    let range = b - a;
+    if range.is_zero() {
+        return (math::mod_pow(g, a, p) == *h).then(|| a.clone());
+    }
+
+    let jumps = build_jumps(&range);
+    let head_start = range.sqrt() + BigUint::one();
+    let hop_budget = &head_start * BigUint::from(HOP_BUDGET_FACTOR);
+
+    // Tame kangaroo: released from g^b, hops forward recording its final
+    // position as a trap for the wild kangaroo to walk into.
+    let (trap, d_tame) = release_kangaroo(g, p, &math::mod_pow(g, b, p), &jumps, &head_start);
+
+    // Wild kangaroo: released from h, hops forward until it lands on the
+    // trap (meaning it and the tame kangaroo now occupy the same position).
+    let mut y_wild = h.clone();
+    let mut d_wild = BigUint::zero();
+    let mut hops = BigUint::zero();
+
+    while hops < hop_budget {
+        if y_wild == trap {
+            let landed_at = b + &d_tame;
+            if landed_at < d_wild {
+                return None;
+            }
+            let x = landed_at - &d_wild;
+            return (math::mod_pow(g, &x, p) == *h).then_some(x);
+        }
+
+        let jump = jumps[selector(&jumps, &y_wild)].clone();
+        d_wild += &jump;
+        y_wild = math::mod_mul(&y_wild, &math::mod_pow(g, &jump, p), p);
+        hops += BigUint::one();
+    }
+
+    None
+}
+
+/// Runs one kangaroo for `hop_count` hops starting at `start`, returning its
+/// final position and the total distance traveled.
+fn release_kangaroo(
+    g: &BigUint,
+    p: &BigUint,
+    start: &BigUint,
+    jumps: &[BigUint],
+    hop_count: &BigUint,
+) -> (BigUint, BigUint) {
+    let mut y = start.clone();
+    let mut distance = BigUint::zero();
+    let mut hops = BigUint::zero();
+
+    while &hops < hop_count {
+        let jump = jumps[selector(jumps, &y)].clone();
+        distance += &jump;
+        y = math::mod_mul(&y, &math::mod_pow(g, &jump, p), p);
+        hops += BigUint::one();
+    }
+
+    (y, distance)
+}
+
+/// Builds a jump set of `k ~ log2(range)` powers of two, scaled so their
+/// mean is close to `sqrt(range) / 2`.
+///
+/// Scaling each power independently by `mean_target / unscaled_mean` and
+/// flooring collapses the smallest several powers to the same sub-`1`
+/// value once that ratio drops below `1 / power`: e.g. for `range = 2000`
+/// (`k = 11`) the floor of the first four terms is `0` and of the fifth is
+/// `1`, and clamping each up to the minimum of `1` turns them into five
+/// duplicate jumps, which
+/// breaks the walk's mixing. Since the powers are strictly increasing, the
+/// floored results are never decreasing either, so instead of clamping
+/// every sub-`1` term to the same floor we walk the sequence and bump each
+/// term up to one more than its predecessor — distinct by construction,
+/// and a no-op wherever the scaling already produced a bigger jump.
+fn build_jumps(range: &BigUint) -> Vec<BigUint> {
+    let k = range.bits().max(1) as usize;
+    let mean_target = {
+        let half = range.sqrt() / BigUint::from(2u32);
+        if half.is_zero() { BigUint::one() } else { half }
+    };
+
+    let powers: Vec<BigUint> = (0..k).map(|i| BigUint::one() << i).collect();
+    let unscaled_mean = {
+        let sum: BigUint = powers.iter().sum();
+        let mean = sum / BigUint::from(k as u32);
+        if mean.is_zero() { BigUint::one() } else { mean }
+    };
+
+    let mut prev = BigUint::zero();
+    powers
+        .into_iter()
+        .map(|power| {
+            let scaled = (&power * &mean_target) / &unscaled_mean;
+            let jump = scaled.max(&prev + BigUint::one());
+            prev = jump.clone();
+            jump
+        })
+        .collect()
+}
+
+/// Deterministic jump selector `s(y) = jumps[y mod k]`.
+fn selector(jumps: &[BigUint], y: &BigUint) -> usize {
+    let k = BigUint::from(jumps.len() as u64);
+    (y % k).to_usize().unwrap_or(0)
+}
@@ -1,38 +1,304 @@
-use dh_crack::{DhKey, crack_dh};
+use dh_crack::{crack_dh, dh_exchange, dh_secret, DhCrackError, DhKey, DhParams};
+use num::bigint::BigUint;
+use rand::RngCore;
+use std::io::{Read, Write};
 use std::process::ExitCode;
 
+/// How a key is read from the command line / stdin and printed back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KeyFormat {
+    Hex,
+    Dec,
+    Bytes,
+}
+
+impl KeyFormat {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "hex" => Some(Self::Hex),
+            "dec" => Some(Self::Dec),
+            "bytes" => Some(Self::Bytes),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum CliError {
+    Usage(String),
+    Dh(DhCrackError),
+}
+
+impl From<DhCrackError> for CliError {
+    fn from(e: DhCrackError) -> Self {
+        CliError::Dh(e)
+    }
+}
+
 fn main() -> ExitCode {
     let args: Vec<String> = std::env::args().collect();
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(CliError::Usage(msg)) => {
+            eprintln!("{msg}");
+            ExitCode::from(1)
+        }
+        Err(CliError::Dh(e)) => {
+            eprintln!("Error: {e}");
+            ExitCode::from(exit_code_for(&e))
+        }
+    }
+}
 
-    if args.len() != 2 {
-        eprintln!("Usage: {} <public_key_hex_le>", args[0]);
-        eprintln!("Example: {} 7b074553b055f69d", args[0]);
-        return ExitCode::from(1);
+/// Distinct exit codes per [`DhCrackError`] variant, so callers scripting
+/// against this CLI can branch on failure class without parsing stderr.
+fn exit_code_for(e: &DhCrackError) -> u8 {
+    match e {
+        DhCrackError::InvalidHex(_) => 2,
+        DhCrackError::InvalidKeyLength { .. } => 3,
+        DhCrackError::ZeroPublicKey => 4,
+        DhCrackError::ModulusNotPrime => 5,
+        DhCrackError::InvalidGenerator => 6,
+        DhCrackError::DiscreteLogFailed => 7,
     }
+}
+
+fn usage(prog: &str) -> String {
+    format!(
+        "Usage: {prog} <command> [args] [--format hex|dec|bytes]\n\
+         \n\
+         Commands:\n\
+         \x20 crack <pubkey>             Recover the private key matching a public key\n\
+         \x20 exchange <privkey>         Derive the public key for a private key\n\
+         \x20 secret <peer_pub> <priv>   Derive the shared secret from a peer's public key and your private key\n\
+         \x20 generate                   Emit a random private key and its public key\n\
+         \n\
+         A key argument of '-' (or omitting a command's only key argument) reads it from stdin.\n\
+         --format defaults to hex and applies to every key read or printed by the command."
+    )
+}
 
-    let hex = &args[1];
+fn run(args: &[String]) -> Result<(), CliError> {
+    let prog = args.first().map(String::as_str).unwrap_or("dh-crack");
+    let command = args.get(1).ok_or_else(|| CliError::Usage(usage(prog)))?;
+    let (positionals, format) = split_format(&args[2..], prog)?;
+    let params = DhParams::default();
 
-    if hex.len() != 16 {
-        eprintln!("Error: public key must be exactly 16 hex characters (8 bytes)");
-        return ExitCode::from(1);
+    match command.as_str() {
+        "crack" => {
+            let public = read_key(at_most_one(&positionals, prog)?, format, &params)?;
+            let private = crack_dh(&params, &public, None)?;
+            print_key(&private, format, &params);
+            Ok(())
+        }
+        "exchange" => {
+            let private = read_key(at_most_one(&positionals, prog)?, format, &params)?;
+            let public = dh_exchange(&params, &private);
+            print_key(&public, format, &params);
+            Ok(())
+        }
+        "secret" => {
+            if positionals.len() != 2 {
+                return Err(CliError::Usage(usage(prog)));
+            }
+            let peer_public = read_key(Some(positionals[0]), format, &params)?;
+            let private = read_key(Some(positionals[1]), format, &params)?;
+            let secret = dh_secret(&params, &peer_public, &private);
+            print_key(&secret, format, &params);
+            Ok(())
+        }
+        "generate" => {
+            if !positionals.is_empty() {
+                return Err(CliError::Usage(usage(prog)));
+            }
+            let private = generate_private_key(&params);
+            let public = dh_exchange(&params, &private);
+            print_key(&private, format, &params);
+            print_key(&public, format, &params);
+            Ok(())
+        }
+        _ => Err(CliError::Usage(usage(prog))),
     }
+}
 
-    let public_key = match DhKey::from_hex_le(hex) {
-        Ok(k) => k,
-        Err(e) => {
-            eprintln!("Error: {}", e);
-            return ExitCode::from(1);
+/// Splits `--format <value>` out of `args`, leaving the remaining positional
+/// key arguments in order.
+fn split_format<'a>(args: &'a [String], prog: &str) -> Result<(Vec<&'a str>, KeyFormat), CliError> {
+    let mut positionals = Vec::new();
+    let mut format = KeyFormat::Hex;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--format" {
+            let value = iter.next().ok_or_else(|| CliError::Usage(usage(prog)))?;
+            format = KeyFormat::parse(value)
+                .ok_or_else(|| CliError::Usage(format!("unknown format '{value}', expected hex, dec, or bytes")))?;
+        } else {
+            positionals.push(arg.as_str());
         }
-    };
+    }
+    Ok((positionals, format))
+}
 
-    match crack_dh(&public_key) {
-        Ok(private_key) => {
-            println!("Private key: {}", private_key.to_hex_le());
-            ExitCode::SUCCESS
+fn at_most_one<'a>(positionals: &[&'a str], prog: &str) -> Result<Option<&'a str>, CliError> {
+    match positionals {
+        [] => Ok(None),
+        [only] => Ok(Some(only)),
+        _ => Err(CliError::Usage(usage(prog))),
+    }
+}
+
+/// Reads a key from `token`, falling back to stdin when `token` is absent or
+/// `"-"` so the CLI composes in pipelines.
+fn read_key(token: Option<&str>, format: KeyFormat, params: &DhParams) -> Result<DhKey, CliError> {
+    match token.filter(|t| *t != "-") {
+        Some(value) => parse_key(value, format, params),
+        None if format == KeyFormat::Bytes => {
+            let mut bytes = Vec::new();
+            std::io::stdin()
+                .read_to_end(&mut bytes)
+                .map_err(|e| CliError::Usage(format!("failed to read stdin: {e}")))?;
+            Ok(DhKey::from_bytes_le(&bytes, params)?)
         }
-        Err(e) => {
-            eprintln!("Error: {}", e);
-            ExitCode::from(1)
+        None => {
+            let mut line = String::new();
+            std::io::stdin()
+                .read_line(&mut line)
+                .map_err(|e| CliError::Usage(format!("failed to read stdin: {e}")))?;
+            parse_key(line.trim(), format, params)
+        }
+    }
+}
+
+fn parse_key(value: &str, format: KeyFormat, params: &DhParams) -> Result<DhKey, CliError> {
+    match format {
+        KeyFormat::Hex => Ok(DhKey::from_hex_le(value, params)?),
+        // Matches the stdin path in `read_key`: the argument's raw bytes
+        // *are* the key, not a hex encoding of it.
+        KeyFormat::Bytes => Ok(DhKey::from_bytes_le(value.as_bytes(), params)?),
+        KeyFormat::Dec => {
+            let n = BigUint::parse_bytes(value.as_bytes(), 10)
+                .ok_or_else(|| CliError::Usage(format!("invalid decimal key: {value}")))?;
+            let mut bytes = n.to_bytes_le();
+            if bytes.len() > params.byte_len() {
+                return Err(CliError::Dh(DhCrackError::InvalidKeyLength {
+                    expected: params.byte_len(),
+                    got: bytes.len(),
+                }));
+            }
+            bytes.resize(params.byte_len(), 0);
+            Ok(DhKey::from_bytes_le(&bytes, params)?)
+        }
+    }
+}
+
+fn print_key(key: &DhKey, format: KeyFormat, params: &DhParams) {
+    match format {
+        KeyFormat::Hex => println!("{}", key.to_hex_le(params)),
+        KeyFormat::Dec => println!("{}", key.as_biguint()),
+        KeyFormat::Bytes => {
+            let bytes = key.to_bytes_le(params);
+            std::io::stdout()
+                .write_all(&bytes)
+                .expect("failed to write key bytes to stdout");
+        }
+    }
+}
+
+/// Draws a uniformly random private key in `[1, modulus)` via rejection
+/// sampling over `params.byte_len()` random bytes.
+fn generate_private_key(params: &DhParams) -> DhKey {
+    let mut rng = rand::thread_rng();
+    let mut bytes = vec![0u8; params.byte_len()];
+    loop {
+        rng.fill_bytes(&mut bytes);
+        if let Ok(key) = DhKey::from_bytes_le(&bytes, params) {
+            if key.as_biguint() < params.modulus() {
+                return key;
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_split_format_extracts_flag_from_anywhere() {
+        let args = strings(&["cbed2a7d9585b611", "--format", "dec"]);
+        let (positionals, format) = split_format(&args, "dh-crack").unwrap();
+        assert_eq!(positionals, vec!["cbed2a7d9585b611"]);
+        assert_eq!(format, KeyFormat::Dec);
+    }
+
+    #[test]
+    fn test_split_format_defaults_to_hex() {
+        let args = strings(&["cbed2a7d9585b611"]);
+        let (positionals, format) = split_format(&args, "dh-crack").unwrap();
+        assert_eq!(positionals, vec!["cbed2a7d9585b611"]);
+        assert_eq!(format, KeyFormat::Hex);
+    }
+
+    #[test]
+    fn test_split_format_rejects_unknown_value() {
+        let args = strings(&["--format", "base64"]);
+        assert!(split_format(&args, "dh-crack").is_err());
+    }
+
+    #[test]
+    fn test_parse_key_bytes_treats_argument_as_raw_bytes() {
+        let params = DhParams::default();
+        let value = "bytesdat"; // exactly params.byte_len() (8) ASCII bytes
+        let key = parse_key(value, KeyFormat::Bytes, &params).unwrap();
+        assert_eq!(key.to_bytes_le(&params), value.as_bytes());
+    }
+
+    #[test]
+    fn test_parse_key_bytes_does_not_hex_decode() {
+        let params = DhParams::default();
+        // 16 ASCII characters is the right length for hex but wrong for an
+        // 8-byte raw key, so this must fail length validation, not silently
+        // succeed by decoding it as hex (the bug this format guards against).
+        let err = parse_key("cbed2a7d9585b611", KeyFormat::Bytes, &params).unwrap_err();
+        assert!(matches!(
+            err,
+            CliError::Dh(DhCrackError::InvalidKeyLength { expected: 8, got: 16 })
+        ));
+    }
+
+    #[test]
+    fn test_parse_key_dec_roundtrips_with_hex() {
+        let params = DhParams::default();
+        let from_hex = parse_key("cbed2a7d9585b611", KeyFormat::Hex, &params).unwrap();
+        let from_dec = parse_key("1276354421502701003", KeyFormat::Dec, &params).unwrap();
+        assert_eq!(from_hex, from_dec);
+    }
+
+    #[test]
+    fn test_exit_code_for_is_distinct_per_variant() {
+        let samples = [
+            DhCrackError::InvalidHex(hex::FromHexError::OddLength),
+            DhCrackError::InvalidKeyLength { expected: 8, got: 4 },
+            DhCrackError::ZeroPublicKey,
+            DhCrackError::ModulusNotPrime,
+            DhCrackError::InvalidGenerator,
+            DhCrackError::DiscreteLogFailed,
+        ];
+        let codes: Vec<u8> = samples.iter().map(exit_code_for).collect();
+        let mut sorted = codes.clone();
+        sorted.sort_unstable();
+        sorted.dedup();
+        assert_eq!(sorted.len(), codes.len());
+    }
+
+    #[test]
+    fn test_generate_private_key_is_in_range() {
+        let params = DhParams::default();
+        let key = generate_private_key(&params);
+        assert!(key.as_biguint() < params.modulus());
+    }
+}
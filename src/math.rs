@@ -0,0 +1,554 @@
+//! Modular arithmetic and discrete-log routines shared by the public API.
+//!
+//! Everything here operates on [`BigUint`] so the same Pohlig-Hellman
+//! machinery works for groups of any size, not just the crate's original
+//! fixed 64-bit modulus.
+
+use num::bigint::{BigInt, BigUint};
+use num::{Integer, One, Zero};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+use crate::montgomery::MontgomeryCtx;
+
+/// Dispatches to the Montgomery-form exponentiation when a context is
+/// available, falling back to plain modular exponentiation otherwise.
+fn pow_with(ctx: Option<&MontgomeryCtx>, base: &BigUint, exp: &BigUint, m: &BigUint) -> BigUint {
+    match ctx {
+        Some(ctx) => ctx.mod_pow(base, exp),
+        None => mod_pow(base, exp, m),
+    }
+}
+
+const SMALL_PRIMES: [u32; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+pub(crate) fn mod_pow(base: &BigUint, exp: &BigUint, m: &BigUint) -> BigUint {
+    if m.is_one() {
+        return BigUint::zero();
+    }
+    base.modpow(exp, m)
+}
+
+pub(crate) fn mod_mul(a: &BigUint, b: &BigUint, m: &BigUint) -> BigUint {
+    (a * b) % m
+}
+
+pub(crate) fn mod_inverse(a: &BigUint, m: &BigUint) -> Option<BigUint> {
+    let mut old_r: BigInt = a.clone().into();
+    let mut r: BigInt = m.clone().into();
+    let mut old_s = BigInt::one();
+    let mut s = BigInt::zero();
+
+    while !r.is_zero() {
+        let q = &old_r / &r;
+        let new_r = &old_r - &q * &r;
+        old_r = r;
+        r = new_r;
+
+        let new_s = &old_s - &q * &s;
+        old_s = s;
+        s = new_s;
+    }
+
+    if old_r != BigInt::one() {
+        return None;
+    }
+
+    let m_int: BigInt = m.clone().into();
+    let result = ((old_s % &m_int) + &m_int) % &m_int;
+    result.to_biguint()
+}
+
+fn isqrt_ceil(n: &BigUint) -> BigUint {
+    let s = n.sqrt();
+    if &s * &s < *n {
+        s + BigUint::one()
+    } else {
+        s
+    }
+}
+
+/// Builds the baby-step table `{g^j: j}` for `j in 0..m`.
+fn build_baby_steps(g: &BigUint, m: &BigUint, p: &BigUint) -> HashMap<BigUint, BigUint> {
+    let mut table = HashMap::new();
+    let mut g_j = BigUint::one();
+    let mut j = BigUint::zero();
+    while j < *m {
+        table.insert(g_j.clone(), j.clone());
+        g_j = mod_mul(&g_j, g, p);
+        j += BigUint::one();
+    }
+    table
+}
+
+/// Bundles the state a giant-step scan needs, so a scan over a sub-range of
+/// `0..m` (for sharding across threads) takes the same few arguments as a
+/// scan over the whole range.
+struct GiantStepScan<'a> {
+    g: &'a BigUint,
+    h: &'a BigUint,
+    p: &'a BigUint,
+    m: &'a BigUint,
+    g_m_inv: &'a BigUint,
+    table: &'a HashMap<BigUint, BigUint>,
+    ctx: Option<&'a MontgomeryCtx>,
+}
+
+/// Number of giant steps between checks of a sibling shard's `found` flag in
+/// [`GiantStepScan::scan`], mirroring [`POLLARD_BATCH`]'s amortized-check
+/// batching: cheap enough to bail out promptly once another shard wins, rare
+/// enough not to dominate the loop with atomic loads.
+const SCAN_CHECK_INTERVAL: u64 = 256;
+
+impl GiantStepScan<'_> {
+    /// Scans giant steps `i in [i_start, i_end)` against `table`, starting
+    /// `gamma` at `h * g_m_inv^i_start` so callers can shard the full
+    /// `0..m` range across threads without recomputing earlier steps.
+    ///
+    /// `found` is the sibling shards' shared early-exit flag: every
+    /// [`SCAN_CHECK_INTERVAL`] steps this checks it and bails out if another
+    /// shard already found the answer, instead of scanning its whole slice
+    /// to completion regardless. `None` when there are no siblings to race
+    /// against (the sequential caller, or a single-shard parallel run).
+    fn scan(&self, i_start: &BigUint, i_end: &BigUint, found: Option<&AtomicBool>) -> Option<BigUint> {
+        let mut gamma = mod_mul(self.h, &pow_with(self.ctx, self.g_m_inv, i_start, self.p), self.p);
+        let mut i = i_start.clone();
+        let mut since_check = 0u64;
+        while i < *i_end {
+            if let Some(found) = found {
+                if since_check >= SCAN_CHECK_INTERVAL {
+                    if found.load(Ordering::Relaxed) {
+                        return None;
+                    }
+                    since_check = 0;
+                }
+            }
+
+            if let Some(j) = self.table.get(&gamma) {
+                let x = &i * self.m + j;
+                if pow_with(self.ctx, self.g, &x, self.p) == *self.h {
+                    return Some(x);
+                }
+            }
+            gamma = mod_mul(&gamma, self.g_m_inv, self.p);
+            i += BigUint::one();
+            since_check += 1;
+        }
+        None
+    }
+}
+
+pub(crate) fn baby_step_giant_step(
+    g: &BigUint,
+    h: &BigUint,
+    p: &BigUint,
+    order: &BigUint,
+    ctx: Option<&MontgomeryCtx>,
+) -> Option<BigUint> {
+    let m = isqrt_ceil(order) + BigUint::one();
+    let table = build_baby_steps(g, &m, p);
+
+    let g_m = pow_with(ctx, g, &m, p);
+    let g_m_inv = mod_inverse(&g_m, p)?;
+
+    let scan = GiantStepScan { g, h, p, m: &m, g_m_inv: &g_m_inv, table: &table, ctx };
+    scan.scan(&BigUint::zero(), &m, None)
+}
+
+/// Like [`baby_step_giant_step`], but shards the giant-step scan across
+/// `threads` worker threads once the baby-step table is built: each thread
+/// computes its own `gamma` offset for its slice of `0..m` and scans it
+/// against the shared, read-only table, returning as soon as any thread
+/// finds a hit.
+pub(crate) fn baby_step_giant_step_parallel(
+    g: &BigUint,
+    h: &BigUint,
+    p: &BigUint,
+    order: &BigUint,
+    ctx: Option<&MontgomeryCtx>,
+    threads: usize,
+) -> Option<BigUint> {
+    let threads = threads.max(1);
+    let m = isqrt_ceil(order) + BigUint::one();
+    let table = build_baby_steps(g, &m, p);
+
+    let g_m = pow_with(ctx, g, &m, p);
+    let g_m_inv = mod_inverse(&g_m, p)?;
+    let scan = GiantStepScan { g, h, p, m: &m, g_m_inv: &g_m_inv, table: &table, ctx };
+
+    if threads == 1 {
+        return scan.scan(&BigUint::zero(), &m, None);
+    }
+
+    let chunk = (&m / BigUint::from(threads as u64)) + BigUint::one();
+    let found = AtomicBool::new(false);
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|t| {
+                let i_start = BigUint::from(t as u64) * &chunk;
+                let i_end = (&i_start + &chunk).min(m.clone());
+                let scan = &scan;
+                let found = &found;
+                scope.spawn(move || {
+                    if i_start >= i_end || found.load(Ordering::Relaxed) {
+                        return None;
+                    }
+                    let hit = scan.scan(&i_start, &i_end, Some(found));
+                    if hit.is_some() {
+                        found.store(true, Ordering::Relaxed);
+                    }
+                    hit
+                })
+            })
+            .collect();
+
+        handles.into_iter().find_map(|handle| handle.join().unwrap())
+    })
+}
+
+/// Upper bound for the initial trial-division sweep in [`factor_order`].
+const SMALL_FACTOR_LIMIT: u32 = 1_000_000;
+/// Number of Brent-cycle steps between batched gcd checks in [`pollard_rho`].
+const POLLARD_BATCH: u64 = 128;
+
+/// Factors `n`, stripping small primes by trial division up to
+/// [`SMALL_FACTOR_LIMIT`] and handling the (possibly large) cofactor with
+/// Miller-Rabin primality testing plus Pollard's rho for composites.
+pub(crate) fn factor_order(n: &BigUint) -> Vec<(BigUint, u32)> {
+    let mut factors: HashMap<BigUint, u32> = HashMap::new();
+    let mut remaining = n.clone();
+
+    let small_limit = BigUint::from(SMALL_FACTOR_LIMIT);
+    let mut d = BigUint::from(2u32);
+    while d <= small_limit && &d * &d <= remaining {
+        if remaining.is_multiple_of(&d) {
+            let mut exp = 0u32;
+            while remaining.is_multiple_of(&d) {
+                remaining /= &d;
+                exp += 1;
+            }
+            *factors.entry(d.clone()).or_insert(0) += exp;
+        }
+        d += BigUint::one();
+    }
+
+    if remaining > BigUint::one() {
+        factor_large(remaining, &mut factors);
+    }
+
+    let mut result: Vec<(BigUint, u32)> = factors.into_iter().collect();
+    result.sort_by(|a, b| a.0.cmp(&b.0));
+    result
+}
+
+/// Recursively splits `n` (known to have no factor below [`SMALL_FACTOR_LIMIT`])
+/// into primes via Miller-Rabin + Pollard's rho, merging into `factors`.
+fn factor_large(n: BigUint, factors: &mut HashMap<BigUint, u32>) {
+    if n <= BigUint::one() {
+        return;
+    }
+    if is_probable_prime(&n) {
+        *factors.entry(n).or_insert(0) += 1;
+        return;
+    }
+
+    let divisor = pollard_rho(&n);
+    let cofactor = &n / &divisor;
+    factor_large(divisor, factors);
+    factor_large(cofactor, factors);
+}
+
+/// Splits a composite `n` into one nontrivial factor using Pollard's rho,
+/// retrying with a fresh `c` whenever a run fails to separate the cycle.
+fn pollard_rho(n: &BigUint) -> BigUint {
+    let two = BigUint::from(2u32);
+    if n.is_multiple_of(&two) {
+        return two;
+    }
+
+    let mut rng = XorShift64::seeded_from(n);
+    loop {
+        let c = {
+            let candidate = BigUint::from(rng.next_u64()) % n;
+            if candidate.is_zero() { BigUint::one() } else { candidate }
+        };
+        let x0 = BigUint::from(rng.next_u64()) % n;
+
+        if let Some(factor) = brent_rho(n, &c, x0) {
+            return factor;
+        }
+    }
+}
+
+/// One attempt of Brent's variant of Pollard's rho for `f(x) = x^2 + c mod n`,
+/// starting from `x0`. Advances the hare in power-of-two step lengths,
+/// batching the running product of `|x - y|` and checking `gcd` once every
+/// [`POLLARD_BATCH`] steps; if a batch's gcd equals `n`, the batch is re-run
+/// one step at a time to pin down the exact factor. Returns `None` if this
+/// particular `(c, x0)` never separates (caller should retry with a fresh `c`).
+fn brent_rho(n: &BigUint, c: &BigUint, x0: BigUint) -> Option<BigUint> {
+    let f = |x: &BigUint| -> BigUint { (mod_mul(x, x, n) + c) % n };
+
+    let mut y = x0;
+    let mut x = y.clone();
+    let mut ys = y.clone();
+    let mut g = BigUint::one();
+    let mut r: u64 = 1;
+
+    while g.is_one() {
+        x = y.clone();
+        for _ in 0..r {
+            y = f(&y);
+        }
+
+        let mut k = 0u64;
+        while k < r && g.is_one() {
+            ys = y.clone();
+            let steps = POLLARD_BATCH.min(r - k);
+            let mut product = BigUint::one();
+            for _ in 0..steps {
+                y = f(&y);
+                product = mod_mul(&product, &abs_diff(&x, &y), n);
+            }
+            g = gcd(&product, n);
+            k += steps;
+        }
+        r *= 2;
+    }
+
+    if &g == n {
+        loop {
+            ys = f(&ys);
+            g = gcd(&abs_diff(&x, &ys), n);
+            if g > BigUint::one() {
+                break;
+            }
+        }
+    }
+
+    if &g == n { None } else { Some(g) }
+}
+
+fn abs_diff(a: &BigUint, b: &BigUint) -> BigUint {
+    if a >= b { a - b } else { b - a }
+}
+
+fn gcd(a: &BigUint, b: &BigUint) -> BigUint {
+    let mut a = a.clone();
+    let mut b = b.clone();
+    while !b.is_zero() {
+        let r = &a % &b;
+        a = b;
+        b = r;
+    }
+    a
+}
+
+/// Small deterministic PRNG used only to pick Pollard's rho parameters;
+/// there is no need for cryptographic randomness here.
+struct XorShift64 {
+    state: u64,
+}
+
+impl XorShift64 {
+    fn seeded_from(n: &BigUint) -> Self {
+        let bytes = n.to_bytes_le();
+        let mut buf = [0u8; 8];
+        let len = bytes.len().min(8);
+        buf[..len].copy_from_slice(&bytes[..len]);
+        let seed = u64::from_le_bytes(buf) ^ 0x9e3779b97f4a7c15;
+        Self {
+            state: if seed == 0 { 1 } else { seed },
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state = x;
+        x
+    }
+}
+
+pub(crate) fn pohlig_hellman(
+    g: &BigUint,
+    h: &BigUint,
+    p: &BigUint,
+    factors: &[(BigUint, u32)],
+    ctx: Option<&MontgomeryCtx>,
+) -> Option<BigUint> {
+    let order = p - BigUint::one();
+
+    let mut residues: Vec<BigUint> = Vec::new();
+    let mut moduli: Vec<BigUint> = Vec::new();
+
+    for (prime, exp) in factors {
+        let prime_power = prime.pow(*exp);
+        let exp_factor = &order / &prime_power;
+        let g_i = pow_with(ctx, g, &exp_factor, p);
+        let h_i = pow_with(ctx, h, &exp_factor, p);
+
+        let x_i = baby_step_giant_step(&g_i, &h_i, p, &prime_power, ctx)?;
+
+        residues.push(x_i);
+        moduli.push(prime_power);
+    }
+
+    let result = chinese_remainder_theorem(&residues, &moduli)?;
+
+    if pow_with(ctx, g, &result, p) == *h {
+        Some(result)
+    } else {
+        None
+    }
+}
+
+/// Like [`pohlig_hellman`], but dispatches the per-prime-power component
+/// DLPs across a thread pool sized to `threads`: each component's
+/// `(residue, modulus)` is independent until the final CRT combine, so each
+/// thread builds and scans its own BSGS table. The component with the
+/// largest prime power dominates wall time, so it additionally gets its
+/// giant-step scan sharded across whatever thread budget remains via
+/// [`baby_step_giant_step_parallel`].
+pub(crate) fn pohlig_hellman_parallel(
+    g: &BigUint,
+    h: &BigUint,
+    p: &BigUint,
+    factors: &[(BigUint, u32)],
+    ctx: Option<&MontgomeryCtx>,
+    threads: usize,
+) -> Option<BigUint> {
+    if factors.is_empty() {
+        return None;
+    }
+    let threads = threads.max(1);
+    let order = p - BigUint::one();
+
+    let prime_powers: Vec<BigUint> = factors.iter().map(|(prime, exp)| prime.pow(*exp)).collect();
+    let dominant = prime_powers
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, power)| (*power).clone())
+        .map(|(idx, _)| idx)
+        .unwrap_or(0);
+    let dominant_threads = threads.saturating_sub(prime_powers.len() - 1).max(1);
+
+    let results: Vec<Option<BigUint>> = thread::scope(|scope| {
+        let handles: Vec<_> = prime_powers
+            .iter()
+            .enumerate()
+            .map(|(idx, prime_power)| {
+                let exp_factor = &order / prime_power;
+                let g_i = pow_with(ctx, g, &exp_factor, p);
+                let h_i = pow_with(ctx, h, &exp_factor, p);
+                let prime_power = prime_power.clone();
+                scope.spawn(move || {
+                    if idx == dominant {
+                        baby_step_giant_step_parallel(&g_i, &h_i, p, &prime_power, ctx, dominant_threads)
+                    } else {
+                        baby_step_giant_step(&g_i, &h_i, p, &prime_power, ctx)
+                    }
+                })
+            })
+            .collect();
+
+        handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+    });
+
+    let mut residues = Vec::with_capacity(results.len());
+    for result in results {
+        residues.push(result?);
+    }
+
+    let result = chinese_remainder_theorem(&residues, &prime_powers)?;
+
+    if pow_with(ctx, g, &result, p) == *h {
+        Some(result)
+    } else {
+        None
+    }
+}
+
+fn chinese_remainder_theorem(residues: &[BigUint], moduli: &[BigUint]) -> Option<BigUint> {
+    let prod: BigUint = moduli.iter().product();
+    let mut sum = BigUint::zero();
+
+    for (r_i, m_i) in residues.iter().zip(moduli.iter()) {
+        let p_i = &prod / m_i;
+        let inv = mod_inverse(&p_i, m_i)?;
+        sum += r_i * &p_i * &inv;
+    }
+
+    Some(sum % prod)
+}
+
+/// Miller-Rabin primality test. Deterministic for every `n < 2^64` given
+/// these witness bases; a strong probabilistic test beyond that range.
+pub(crate) fn is_probable_prime(n: &BigUint) -> bool {
+    let two = BigUint::from(2u32);
+    if *n < two {
+        return false;
+    }
+
+    for &p in &SMALL_PRIMES {
+        let p_big = BigUint::from(p);
+        if *n == p_big {
+            return true;
+        }
+        if n.is_multiple_of(&p_big) {
+            return false;
+        }
+    }
+
+    let one = BigUint::one();
+    let n_minus_one = n - &one;
+    let mut d = n_minus_one.clone();
+    let mut r = 0u32;
+    while d.is_multiple_of(&two) {
+        d /= &two;
+        r += 1;
+    }
+
+    SMALL_PRIMES.iter().all(|&a| {
+        let a_big = BigUint::from(a);
+        a_big >= *n || miller_rabin_witness(n, &a_big, &d, r, &n_minus_one)
+    })
+}
+
+fn miller_rabin_witness(n: &BigUint, a: &BigUint, d: &BigUint, r: u32, n_minus_one: &BigUint) -> bool {
+    let mut x = mod_pow(a, d, n);
+    if x.is_one() || x == *n_minus_one {
+        return true;
+    }
+    for _ in 1..r {
+        x = mod_pow(&x, &BigUint::from(2u32), n);
+        if x == *n_minus_one {
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_factor_order_composite_cofactor_needs_pollard_rho() {
+        // Both factors sit above SMALL_FACTOR_LIMIT and their product has no
+        // smaller factor, so trial division strips nothing and factor_large
+        // must actually split the composite via pollard_rho/brent_rho rather
+        // than taking the "cofactor is already prime" shortcut.
+        let p1 = BigUint::from(549755826239u64); // 40-bit prime
+        let p2 = BigUint::from(1099511726609u64); // 41-bit prime
+        let n = &p1 * &p2;
+
+        assert!(is_probable_prime(&p1));
+        assert!(is_probable_prime(&p2));
+        assert_eq!(factor_order(&n), vec![(p1, 1), (p2, 1)]);
+    }
+}
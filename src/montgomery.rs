@@ -0,0 +1,155 @@
+//! Montgomery-form modular arithmetic.
+//!
+//! `mod_pow`'s inner loop does one `BigUint` division per multiply. A
+//! [`MontgomeryCtx`] built once per modulus replaces that division with a
+//! REDC step (two multiplies, a reduction and a division by the power of two
+//! `R` instead of by the modulus, one conditional subtract), which
+//! pays off heavily across the repeated exponentiations that dominate
+//! BSGS and Pohlig-Hellman.
+
+use num::bigint::BigUint;
+use num::{Integer, One, Zero};
+
+/// Precomputed Montgomery reduction context for a fixed odd modulus.
+///
+/// `R = 2^r_bits` is chosen as the smallest power of two, rounded up to a
+/// multiple of 64 bits, that exceeds the modulus - wide enough for moduli
+/// near a 64- or 128-bit word boundary, not just the crate's original fixed
+/// 64-bit group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MontgomeryCtx {
+    modulus: BigUint,
+    r: BigUint,
+    /// `-modulus^{-1} mod R`, i.e. `n'` in the standard REDC write-up.
+    n_prime: BigUint,
+    /// `R^2 mod modulus`, used to bring operands into Montgomery form.
+    r2_mod_m: BigUint,
+}
+
+impl MontgomeryCtx {
+    /// Builds a context for `modulus`. Returns `None` for even moduli (`R`
+    /// must be coprime to the modulus), in which case callers should fall
+    /// back to plain modular exponentiation.
+    pub(crate) fn new(modulus: &BigUint) -> Option<Self> {
+        if modulus.is_even() {
+            return None;
+        }
+
+        let word_bits = 64u64;
+        let r_bits = modulus.bits().max(1).div_ceil(word_bits) * word_bits;
+        let r = BigUint::one() << r_bits;
+
+        let m_inv = mod_inverse_pow2(modulus, r_bits);
+        let n_prime = (&r - &m_inv) % &r;
+        let r2_mod_m = (&r * &r) % modulus;
+
+        Some(Self {
+            modulus: modulus.clone(),
+            r,
+            n_prime,
+            r2_mod_m,
+        })
+    }
+
+    /// `t * R^-1 mod modulus`, computed without division by `modulus`.
+    fn redc(&self, t: &BigUint) -> BigUint {
+        let t_mod_r = t % &self.r;
+        let q = (&t_mod_r * &self.n_prime) % &self.r;
+        let u = (t + &q * &self.modulus) / &self.r;
+        if u >= self.modulus {
+            u - &self.modulus
+        } else {
+            u
+        }
+    }
+
+    fn to_montgomery(&self, a: &BigUint) -> BigUint {
+        self.redc(&(a * &self.r2_mod_m))
+    }
+
+    // Named to mirror `to_montgomery` above, not as a `from_*` conversion
+    // constructor, so this intentionally takes `&self` rather than no self.
+    #[allow(clippy::wrong_self_convention)]
+    fn from_montgomery(&self, a_mont: &BigUint) -> BigUint {
+        self.redc(a_mont)
+    }
+
+    fn mont_mul(&self, a_mont: &BigUint, b_mont: &BigUint) -> BigUint {
+        self.redc(&(a_mont * b_mont))
+    }
+
+    /// Modular exponentiation, with every squaring and multiply done in
+    /// Montgomery form.
+    pub(crate) fn mod_pow(&self, base: &BigUint, exp: &BigUint) -> BigUint {
+        let base = base % &self.modulus;
+        let mut result_mont = self.to_montgomery(&BigUint::one());
+        let mut base_mont = self.to_montgomery(&base);
+        let mut exp = exp.clone();
+
+        while !exp.is_zero() {
+            if exp.is_odd() {
+                result_mont = self.mont_mul(&result_mont, &base_mont);
+            }
+            base_mont = self.mont_mul(&base_mont, &base_mont);
+            exp >>= 1;
+        }
+
+        self.from_montgomery(&result_mont)
+    }
+}
+
+/// Computes `m^-1 mod 2^bits` for odd `m` via Newton's iteration: starting
+/// from the mod-2 inverse (always `1`), each step doubles the number of
+/// correct low bits via `x = x * (2 - m*x) mod 2^(2k)`.
+fn mod_inverse_pow2(m: &BigUint, bits: u64) -> BigUint {
+    let two = BigUint::from(2u32);
+    let mut x = BigUint::one();
+    let mut cur_bits = 1u64;
+
+    while cur_bits < bits {
+        cur_bits = (cur_bits * 2).min(bits);
+        let modulus = BigUint::one() << cur_bits;
+        let mx = (m * &x) % &modulus;
+        let two_minus_mx = if two >= mx {
+            &two - &mx
+        } else {
+            &modulus - (&mx - &two)
+        };
+        x = (&x * &two_minus_mx) % &modulus;
+    }
+
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math;
+
+    #[test]
+    fn test_mod_pow_matches_plain_mod_pow_for_64bit_modulus() {
+        // `DhParams::default`'s modulus, 2^64 - 59: exactly one 64-bit word,
+        // like every `DhParams` modulus in the crate's other tests.
+        let modulus = (BigUint::one() << 64) - BigUint::from(59u32);
+        let ctx = MontgomeryCtx::new(&modulus).unwrap();
+        let base = BigUint::from(5u32);
+        let exp = BigUint::from(1_276_354_421_502_701_003u64);
+
+        assert_eq!(ctx.mod_pow(&base, &exp), math::mod_pow(&base, &exp, &modulus));
+    }
+
+    #[test]
+    fn test_mod_pow_matches_plain_mod_pow_for_wide_modulus() {
+        // 2^127 - 1, a 127-bit Mersenne prime. `r_bits` rounds up to 128,
+        // spanning two 64-bit words instead of the one every other test in
+        // the crate exercises (every `DhParams` elsewhere has a modulus that
+        // fits one 64-bit word) - this is the path the 128-/256-bit-modulus
+        // support this module adds is actually for.
+        let modulus = (BigUint::one() << 127) - BigUint::one();
+        let ctx = MontgomeryCtx::new(&modulus).unwrap();
+        let base = BigUint::from(123_456_789u64) * BigUint::from(987_654_321u64);
+        let exp = BigUint::from(u64::MAX) + BigUint::from(42u32);
+
+        assert_eq!(ctx.mod_pow(&base, &exp), math::mod_pow(&base, &exp, &modulus));
+    }
+}
@@ -0,0 +1,84 @@
+//! DH group parameters: the modulus/generator pair every operation runs over.
+
+use num::bigint::BigUint;
+use num::{One, Zero};
+
+use crate::math;
+use crate::montgomery::MontgomeryCtx;
+use crate::{DhCrackError, Result};
+
+/// A Diffie-Hellman group: a prime modulus and a generator of a subgroup of
+/// known order.
+///
+/// [`DhParams::default`] reproduces the crate's original fixed group
+/// (`2^64 - 59`, generator `5`), so existing callers keep working unchanged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DhParams {
+    modulus: BigUint,
+    generator: BigUint,
+    order_factors: Vec<(BigUint, u32)>,
+    montgomery: Option<MontgomeryCtx>,
+}
+
+impl DhParams {
+    /// Builds a group, validating that `modulus` is prime and `generator`
+    /// lies in `(0, modulus)`. The order `modulus - 1` is factored eagerly so
+    /// every later discrete-log call can reuse the factorization.
+    pub fn new(modulus: BigUint, generator: BigUint) -> Result<Self> {
+        if !math::is_probable_prime(&modulus) {
+            return Err(DhCrackError::ModulusNotPrime);
+        }
+        if generator.is_zero() || generator >= modulus {
+            return Err(DhCrackError::InvalidGenerator);
+        }
+
+        let order = &modulus - BigUint::one();
+        let order_factors = math::factor_order(&order);
+        let montgomery = MontgomeryCtx::new(&modulus);
+
+        Ok(Self {
+            modulus,
+            generator,
+            order_factors,
+            montgomery,
+        })
+    }
+
+    pub fn modulus(&self) -> &BigUint {
+        &self.modulus
+    }
+
+    pub fn generator(&self) -> &BigUint {
+        &self.generator
+    }
+
+    pub(crate) fn order_factors(&self) -> &[(BigUint, u32)] {
+        &self.order_factors
+    }
+
+    pub(crate) fn montgomery(&self) -> Option<&MontgomeryCtx> {
+        self.montgomery.as_ref()
+    }
+
+    /// Modular exponentiation in this group, routed through the cached
+    /// [`MontgomeryCtx`] when available.
+    pub(crate) fn mod_pow(&self, base: &BigUint, exp: &BigUint) -> BigUint {
+        match &self.montgomery {
+            Some(ctx) => ctx.mod_pow(base, exp),
+            None => math::mod_pow(base, exp, &self.modulus),
+        }
+    }
+
+    /// Width, in bytes, of a key belonging to this group (i.e. of `modulus`).
+    pub fn byte_len(&self) -> usize {
+        self.modulus.bits().div_ceil(8) as usize
+    }
+}
+
+impl Default for DhParams {
+    /// The crate's original fixed group: `2^64 - 59` with generator `5`.
+    fn default() -> Self {
+        Self::new(BigUint::from(crate::MODULUS), BigUint::from(crate::GENERATOR))
+            .expect("default DH group has a prime modulus and a valid generator")
+    }
+}